@@ -1,7 +1,98 @@
 #![deny(clippy::pedantic)]
 
+use std::borrow::Cow;
+use std::io;
+
 use html5ever::tendril::TendrilSink;
-use html5ever::{parse_document, ParseOpts};
+use html5ever::{parse_document, parse_fragment, Attribute, ParseOpts, QualName};
+
+/// Options controlling how text is extracted by [`strip_html_tags_with_options`].
+///
+/// The default value of `Options` reproduces the behavior of [`strip_html_tags`], i.e. all text
+/// nodes are concatenated verbatim.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    /// Insert a newline around block-level elements (`div`, `p`, `li`, `br`, `tr`, headings,
+    /// ...) instead of letting their text run into the text of neighboring elements.
+    pub block_separators: bool,
+    /// Which "raw text" / metadata elements to drop the text content of.
+    pub excluded_tags: ExcludedTags,
+    /// Surfaces text for replaced/embedded content (`<img>`, `<a>`) that would otherwise vanish
+    /// from the output.
+    pub media: MediaOptions,
+}
+
+/// Selects which attributes of replaced/embedded content are appended to the output text.
+///
+/// Every field defaults to `false`, so by default no attribute text is appended, matching
+/// [`strip_html_tags`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MediaOptions {
+    /// Append an `<img>` element's `alt` attribute text.
+    pub alt: bool,
+    /// Append an `<a>` element's `href` attribute, formatted as `text (href)`.
+    pub href: bool,
+    /// Append an `<img>` or `<a>` element's `title` attribute alongside `alt`/`href`.
+    pub title: bool,
+}
+
+/// An element whose text content can be dropped via [`ExcludedTags`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExcludedTag {
+    /// `<script>`.
+    Script,
+    /// `<style>`.
+    Style,
+    /// `<noscript>`.
+    Noscript,
+    /// `<template>`.
+    Template,
+    /// `<head>`.
+    Head,
+    /// `<title>`.
+    Title,
+}
+
+/// Selects which "raw text" / metadata elements have their text content dropped instead of
+/// appended to the output.
+///
+/// Only text whose immediate parent is an excluded element is dropped; text inside an element
+/// nested in it (the `<title>` inside `<head>`, a `<p>` inside `<template>`) is kept unless that
+/// nested element is itself excluded.
+///
+/// Starts out empty, so by default no element's text is dropped, matching [`strip_html_tags`].
+/// Build one up with [`ExcludedTags::with`], e.g.
+/// `ExcludedTags::default().with(ExcludedTag::Script).with(ExcludedTag::Style)`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExcludedTags(u8);
+
+impl ExcludedTags {
+    /// Returns a copy of `self` with `tag` added to the excluded set.
+    #[must_use]
+    pub fn with(mut self, tag: ExcludedTag) -> Self {
+        self.0 |= 1 << tag as u8;
+        self
+    }
+
+    /// Returns whether `name` is one of the elements this set excludes.
+    fn contains(self, name: &QualName) -> bool {
+        let tag = match name.local.as_ref() {
+            "script" => ExcludedTag::Script,
+            "style" => ExcludedTag::Style,
+            "noscript" => ExcludedTag::Noscript,
+            "template" => ExcludedTag::Template,
+            "head" => ExcludedTag::Head,
+            "title" => ExcludedTag::Title,
+            _ => return false,
+        };
+        self.contains_tag(tag)
+    }
+
+    /// Returns whether `tag` is in this set.
+    fn contains_tag(self, tag: ExcludedTag) -> bool {
+        self.0 & (1 << tag as u8) != 0
+    }
+}
 
 /// Consumes a string that contains HTML5 tags and outputs a `String` containing the text content
 /// inside the tags.
@@ -16,7 +107,105 @@ use html5ever::{parse_document, ParseOpts};
 /// ```
 #[must_use]
 pub fn strip_html_tags(input: &str) -> String {
-    parse_document(sink::TextOnly::default(), ParseOpts::default()).one(input)
+    strip_html_tags_with_options(input, Options::default())
+}
+
+/// Like [`strip_html_tags`] but with configurable [`Options`].
+///
+/// Basic usage:
+///
+/// ```rust
+/// # use dissolve::{strip_html_tags_with_options, Options};
+/// let input = "<p>Hello</p><p>World!</p>";
+/// let options = Options { block_separators: true, ..Options::default() };
+/// let output = strip_html_tags_with_options(input, options);
+/// assert_eq!(output, "Hello\nWorld!");
+/// ```
+#[must_use]
+pub fn strip_html_tags_with_options(input: &str, options: Options) -> String {
+    let (text, _errors) =
+        parse_document(sink::TextOnly::new(options), ParseOpts::default()).one(input);
+    text
+}
+
+/// A parse error reported by html5ever while parsing the input, together with the line it
+/// occurred on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// The one-based line number the error occurred on.
+    pub line: u64,
+    /// A description of the error.
+    pub message: Cow<'static, str>,
+}
+
+/// Like [`strip_html_tags`], but also returns the parse errors html5ever encountered while
+/// parsing `input`, so malformed input can be flagged instead of silently accepted.
+///
+/// Basic usage:
+///
+/// ```rust
+/// # use dissolve::strip_html_tags_with_errors;
+/// let input = "<html>a<b</html>";
+/// let (output, errors) = strip_html_tags_with_errors(input);
+/// assert_eq!(output, "a");
+/// assert!(!errors.is_empty());
+/// ```
+#[must_use]
+pub fn strip_html_tags_with_errors(input: &str) -> (String, Vec<ParseError>) {
+    parse_document(sink::TextOnly::new(Options::default()), ParseOpts::default()).one(input)
+}
+
+/// Strips tags from an HTML fragment, parsed as if it were the inner HTML of `context`.
+///
+/// Unlike [`strip_html_tags`], which always goes through `parse_document` and implies a
+/// surrounding `<html>`/`<body>`, this drives `html5ever::parse_fragment` so tree-building
+/// behaves as it would for the inner HTML of a single element, e.g. the contents of one `<td>`
+/// or a chunk of user comment markup.
+///
+/// Basic usage:
+///
+/// ```rust
+/// # use dissolve::strip_html_fragment;
+/// use html5ever::{namespace_url, ns, LocalName, QualName};
+///
+/// let context = QualName::new(None, ns!(html), LocalName::from("td"));
+/// let input = "<b>Hello</b> World!";
+/// let output = strip_html_fragment(input, context);
+/// assert_eq!(output, "Hello World!");
+/// ```
+#[must_use]
+pub fn strip_html_fragment(input: &str, context: QualName) -> String {
+    let (text, _errors) = parse_fragment(
+        sink::TextOnly::new(Options::default()),
+        ParseOpts::default(),
+        context,
+        Vec::<Attribute>::new(),
+    )
+    .one(input);
+    text
+}
+
+/// Like [`strip_html_tags`], but reads the input incrementally from `reader` instead of
+/// requiring it to already be buffered in memory as a `&str`.
+///
+/// Basic usage:
+///
+/// ```rust
+/// # use dissolve::strip_html_tags_reader;
+/// let input = b"<html>Hello World!</html>".as_slice();
+/// let output = strip_html_tags_reader(input).unwrap();
+/// assert_eq!(output, "Hello World!");
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if reading from `reader` fails.
+pub fn strip_html_tags_reader<R: io::Read>(mut reader: R) -> io::Result<String> {
+    let (text, _errors) =
+        parse_document(sink::TextOnly::new(Options::default()), ParseOpts::default())
+            .from_utf8()
+            .read_from(&mut reader)?;
+    Ok(text)
 }
 
 mod sink {
@@ -28,18 +217,266 @@ mod sink {
     use html5ever::tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink};
     use html5ever::{Attribute, ExpandedName, QualName};
 
-    #[derive(Default)]
+    use super::{ExcludedTag, Options, ParseError};
+
     pub struct TextOnly {
         text: RefCell<String>,
+        options: Options,
+        current_line: RefCell<u64>,
+        errors: RefCell<Vec<ParseError>>,
+        /// Ancestor chain, root first, of the node that most recently had text appended to it.
+        /// Diffed against on the next text append by [`Self::transition_ancestor_chain`] to insert
+        /// block separators and flush `link_suffix`es in the right place.
+        active_chain: RefCell<Vec<Handle>>,
+    }
+
+    impl TextOnly {
+        pub fn new(options: Options) -> Self {
+            Self {
+                text: RefCell::default(),
+                options,
+                current_line: RefCell::new(1),
+                errors: RefCell::default(),
+                active_chain: RefCell::default(),
+            }
+        }
+
+        /// Pushes a newline onto the buffer, unless it is empty or already ends in whitespace.
+        ///
+        /// This keeps leading separators out of the output and collapses runs of separators
+        /// emitted by deeply nested block elements into a single newline.
+        fn push_separator(&self) {
+            let mut text = self.text.borrow_mut();
+            if !text.is_empty() && !text.ends_with(char::is_whitespace) {
+                text.push('\n');
+            }
+        }
+
+        /// Builds an `<img>`'s `alt`/`title` attribute text, to be appended by
+        /// [`Self::append_void_text`] once this element is attached to its parent.
+        ///
+        /// Unlike the `<a>` case handled by [`Self::link_suffix`], `<img>` is a void element with
+        /// no children, so it has no text of its own to flush on exit; its text is computed here,
+        /// at creation time, but only actually appended once `append` knows its parent.
+        fn img_text(&self, attrs: &[Attribute]) -> Option<String> {
+            let alt = self.options.media.alt.then(|| attr_value(attrs, "alt")).flatten();
+            let title = self.options.media.title.then(|| attr_value(attrs, "title")).flatten();
+            let text = match (alt, title) {
+                (Some(alt), Some(title)) if !alt.is_empty() => format!("{alt} ({title})"),
+                (Some(alt), None) => alt.to_owned(),
+                (_, Some(title)) => title.to_owned(),
+                (None, None) => return None,
+            };
+            (!text.is_empty()).then_some(text)
+        }
+
+        /// Appends a void element's text (currently only `<img>`'s, via [`Self::img_text`]) to the
+        /// buffer on behalf of `parent`, flushing any `link_suffix`es `parent`'s chain has exited.
+        ///
+        /// This has to go through the same ancestor-chain bookkeeping as [`Self::append_text`]:
+        /// an `<a>` whose only content is an `<img>` never gets an `AppendText` call, so without
+        /// this its `link_suffix` would never be recognized as entered, and thus never flushed.
+        fn append_void_text(&self, parent: &Handle, text: &str) {
+            self.transition_ancestor_chain(&ancestor_chain(parent));
+            let mut buf = self.text.borrow_mut();
+            if !buf.is_empty() && !buf.ends_with(char::is_whitespace) {
+                buf.push(' ');
+            }
+            buf.push_str(text);
+        }
+
+        /// Builds the `" (href)"`/`" (title)"` suffix for an `<a>` element, to be flushed by
+        /// [`Self::transition_ancestor_chain`] once this element's text, including that of any
+        /// nested elements, is done being appended.
+        fn link_suffix(&self, attrs: &[Attribute]) -> Option<String> {
+            let href = self
+                .options
+                .media
+                .href
+                .then(|| attr_value(attrs, "href"))
+                .flatten()
+                .filter(|href| !href.is_empty());
+            let title = self
+                .options
+                .media
+                .title
+                .then(|| attr_value(attrs, "title"))
+                .flatten()
+                .filter(|title| !title.is_empty());
+            match (href, title) {
+                (Some(href), Some(title)) => Some(format!(" ({href}, {title})")),
+                (Some(href), None) => Some(format!(" ({href})")),
+                (None, Some(title)) => Some(format!(" ({title})")),
+                (None, None) => None,
+            }
+        }
+
+        /// Attaches `node` to `parent` and, if `node` is a void element with its own text (an
+        /// `<img>`), appends that text. Shared by `append` and `append_based_on_parent_node`.
+        fn append_node(&self, parent: &Handle, node: &Handle) {
+            *node.parent.borrow_mut() = Some(Rc::clone(parent));
+            if let NodeData::Element {
+                void_text: Some(text),
+                ..
+            } = &node.data
+            {
+                self.append_void_text(parent, text);
+            }
+        }
+
+        /// Appends `text` to the buffer on behalf of `parent`, honoring `excluded_tags` and
+        /// `block_separators` and flushing any `link_suffix`es `parent`'s chain has exited.
+        ///
+        /// Shared by `append` and `append_based_on_parent_node` so neither path can silently
+        /// ignore these options.
+        fn append_text(&self, parent: &Handle, text: &str) {
+            match &parent.data {
+                NodeData::Element { name, .. } if self.options.excluded_tags.contains(name) => {
+                    return;
+                }
+                NodeData::TemplateContents
+                    if self.options.excluded_tags.contains_tag(ExcludedTag::Template) =>
+                {
+                    return;
+                }
+                _ => {}
+            }
+            self.transition_ancestor_chain(&ancestor_chain(parent));
+            self.text.borrow_mut().push_str(text);
+        }
+
+        /// Pushes a separator for `node` if it's a block-level element and `block_separators` is
+        /// on.
+        fn maybe_push_block_separator(&self, node: &Handle) {
+            if self.options.block_separators {
+                if let NodeData::Element { name, .. } = &node.data {
+                    if is_block_level(name) {
+                        self.push_separator();
+                    }
+                }
+            }
+        }
+
+        /// Moves `self.active_chain` to `new_chain`, inserting block separators and flushing
+        /// `link_suffix`es for the ancestors this crosses in and out of.
+        ///
+        /// Checking only a text node's immediate parent, as `append` used to, catches a block
+        /// element directly wrapping text but misses a block element closing right before a text
+        /// sibling, or block-level text nested inside an inline element (`<p><span>a</span></p>`).
+        /// Diffing the whole ancestor chain on every text append catches both: an ancestor present
+        /// in the old chain but not the new one has just been left (innermost first, since that's
+        /// closing order), and one present in the new chain but not the old has just been entered.
+        ///
+        /// html5ever doesn't reliably call [`TreeSink::pop`] when an `<a>` closes: its
+        /// adoption-agency algorithm (used for all formatting elements, including `<a>`) removes
+        /// it from the stack of open elements directly, without going through `pop`, whenever
+        /// there's no "furthest block" to reparent, which is the case for an ordinary
+        /// `<a>text</a>` with no misnested markup inside it. Diffing the ancestor chain on every
+        /// text append is what's left to find the point where a link's content, and thus its
+        /// suffix, is done.
+        fn transition_ancestor_chain(&self, new_chain: &[Handle]) {
+            let old_chain = self.active_chain.replace(new_chain.to_vec());
+            let common_len = old_chain
+                .iter()
+                .zip(new_chain)
+                .take_while(|(old, new)| Rc::ptr_eq(old, new))
+                .count();
+            for node in old_chain[common_len..].iter().rev() {
+                self.maybe_push_block_separator(node);
+                if let NodeData::Element {
+                    link_suffix: Some(suffix),
+                    ..
+                } = &node.data
+                {
+                    self.text.borrow_mut().push_str(suffix);
+                }
+            }
+            for node in &new_chain[common_len..] {
+                self.maybe_push_block_separator(node);
+            }
+        }
+    }
+
+    /// Returns the chain of ancestors from the document root down to `node` inclusive, following
+    /// the parent pointers recorded by `append`'s `AppendNode` case.
+    fn ancestor_chain(node: &Handle) -> Vec<Handle> {
+        let mut chain = vec![Rc::clone(node)];
+        let mut current = Rc::clone(node);
+        loop {
+            let parent = current.parent.borrow().clone();
+            match parent {
+                Some(parent) => {
+                    chain.push(Rc::clone(&parent));
+                    current = parent;
+                }
+                None => break,
+            }
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Returns the value of the attribute named `local_name`, if present.
+    fn attr_value<'a>(attrs: &'a [Attribute], local_name: &str) -> Option<&'a str> {
+        attrs
+            .iter()
+            .find(|attr| attr.name.local.as_ref() == local_name)
+            .map(|attr| attr.value.as_ref())
+    }
+
+    /// Local names of the block-level elements that [`Options::block_separators`] inserts
+    /// newlines around.
+    fn is_block_level(name: &QualName) -> bool {
+        matches!(
+            name.local.as_ref(),
+            "address"
+                | "article"
+                | "aside"
+                | "blockquote"
+                | "dd"
+                | "details"
+                | "dialog"
+                | "div"
+                | "dl"
+                | "dt"
+                | "fieldset"
+                | "figcaption"
+                | "figure"
+                | "footer"
+                | "form"
+                | "h1"
+                | "h2"
+                | "h3"
+                | "h4"
+                | "h5"
+                | "h6"
+                | "header"
+                | "hr"
+                | "li"
+                | "main"
+                | "nav"
+                | "ol"
+                | "p"
+                | "pre"
+                | "section"
+                | "tr"
+                | "ul"
+        )
     }
 
     pub struct Node {
         data: NodeData,
+        /// Set by `append`'s `AppendNode` case once this node is attached to its parent. Lets
+        /// [`ancestor_chain`] walk up from any node to the root.
+        parent: RefCell<Option<Handle>>,
     }
 
     impl Node {
         fn new(data: NodeData) -> Rc<Self> {
-            Rc::new(Self { data })
+            Rc::new(Self {
+                data,
+                parent: RefCell::new(None),
+            })
         }
     }
 
@@ -47,7 +484,22 @@ mod sink {
         Document,
         Comment,
         ProcessingInstruction,
-        Element { name: QualName },
+        Element {
+            name: QualName,
+            /// Text stashed by `create_element` for an `<a>` element when
+            /// `MediaOptions::href`/`title` are enabled, flushed by
+            /// [`TextOnly::transition_ancestor_chain`] once this element's own text, and that of
+            /// any nested elements, is done being appended.
+            link_suffix: Option<String>,
+            /// Text stashed by `create_element` for an `<img>` element when
+            /// `MediaOptions::alt`/`title` are enabled, appended by [`TextOnly::append_node`] once
+            /// this element is attached to its parent.
+            void_text: Option<String>,
+        },
+        /// The contents document of a `<template>` element, as returned by
+        /// `get_template_contents`. Kept distinct from `Document` so `append` can tell the
+        /// difference and honor `ExcludedTag::Template`.
+        TemplateContents,
     }
 
     type Handle = Rc<Node>;
@@ -55,13 +507,32 @@ mod sink {
     impl TreeSink for TextOnly {
         type Handle = Handle;
         type ElemName<'a> = ExpandedName<'a>;
-        type Output = String;
+        type Output = (String, Vec<ParseError>);
 
         fn finish(self) -> Self::Output {
-            self.text.into_inner()
+            for node in self.active_chain.borrow().iter().rev() {
+                if let NodeData::Element {
+                    link_suffix: Some(suffix),
+                    ..
+                } = &node.data
+                {
+                    self.text.borrow_mut().push_str(suffix);
+                }
+            }
+            (self.text.into_inner(), self.errors.into_inner())
+        }
+
+        fn parse_error(&self, msg: Cow<'static, str>) {
+            let line = *self.current_line.borrow();
+            self.errors.borrow_mut().push(ParseError {
+                line,
+                message: msg,
+            });
         }
 
-        fn parse_error(&self, _msg: Cow<'static, str>) {}
+        fn set_current_line(&self, line: u64) {
+            *self.current_line.borrow_mut() = line;
+        }
 
         fn get_document(&self) -> Self::Handle {
             Node::new(NodeData::Document)
@@ -69,7 +540,7 @@ mod sink {
 
         fn elem_name<'a>(&'a self, target: &'a Self::Handle) -> Self::ElemName<'_> {
             match &target.data {
-                NodeData::Element { name } => name.expanded(),
+                NodeData::Element { name, .. } => name.expanded(),
                 _ => panic!("not an element!"),
             }
         }
@@ -77,10 +548,27 @@ mod sink {
         fn create_element(
             &self,
             name: QualName,
-            _attrs: Vec<Attribute>,
+            attrs: Vec<Attribute>,
             _flags: ElementFlags,
         ) -> Self::Handle {
-            Node::new(NodeData::Element { name })
+            if self.options.block_separators && name.local.as_ref() == "br" {
+                self.push_separator();
+            }
+            let void_text = if name.local.as_ref() == "img" {
+                self.img_text(&attrs)
+            } else {
+                None
+            };
+            let link_suffix = if name.local.as_ref() == "a" {
+                self.link_suffix(&attrs)
+            } else {
+                None
+            };
+            Node::new(NodeData::Element {
+                name,
+                link_suffix,
+                void_text,
+            })
         }
 
         fn create_comment(&self, _text: StrTendril) -> Self::Handle {
@@ -99,20 +587,26 @@ mod sink {
         ) {
         }
 
-        fn append(&self, _parent: &Self::Handle, child: NodeOrText<Self::Handle>) {
-            if let NodeOrText::AppendText(text) = &child {
-                self.text.borrow_mut().push_str(text);
+        fn append(&self, parent: &Self::Handle, child: NodeOrText<Self::Handle>) {
+            match child {
+                NodeOrText::AppendNode(node) => self.append_node(parent, &node),
+                NodeOrText::AppendText(text) => self.append_text(parent, &text),
             }
         }
 
         fn append_based_on_parent_node(
             &self,
-            _element: &Self::Handle,
+            element: &Self::Handle,
             _prev_element: &Self::Handle,
             child: NodeOrText<Self::Handle>,
         ) {
-            if let NodeOrText::AppendText(text) = &child {
-                self.text.borrow_mut().push_str(text);
+            // html5ever calls this instead of `append` for table-foster-parented content, e.g.
+            // text found directly inside a `<table>`, before any `<tr>`/`<td>`. `element` is close
+            // enough to a real parent for our purposes, so route through the same helper `append`
+            // uses instead of pushing the text unfiltered.
+            match child {
+                NodeOrText::AppendNode(node) => self.append_node(element, &node),
+                NodeOrText::AppendText(text) => self.append_text(element, &text),
             }
         }
 
@@ -127,7 +621,7 @@ mod sink {
         }
 
         fn get_template_contents(&self, _target: &Self::Handle) -> Self::Handle {
-            Node::new(NodeData::Document)
+            Node::new(NodeData::TemplateContents)
         }
 
         fn same_node(&self, x: &Self::Handle, y: &Self::Handle) -> bool {
@@ -147,6 +641,7 @@ mod sink {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use html5ever::{namespace_url, ns, LocalName};
     #[test]
     fn test_strip_html_tag() {
         let input = "<html>Hello World!</html>";
@@ -177,34 +672,193 @@ mod tests {
 
     #[test]
     fn strip_nested_a() {
-        let input = r#"<html><a>a<a>b</a>c</a></html>"#;
+        let input = r"<html><a>a<a>b</a>c</a></html>";
         let output = strip_html_tags(input);
         assert_eq!(output, "abc");
     }
 
     #[test]
     fn strip_table() {
-        let input = r#"<html>a<table> b<tr> <td>c</td> </tr>d </table>e</html>"#;
+        let input = r"<html>a<table> b<tr> <td>c</td> </tr>d </table>e</html>";
         let output = strip_html_tags(input);
         assert_eq!(output, "a b c d e");
     }
 
+    #[test]
+    fn strip_fragment() {
+        let context = QualName::new(None, ns!(html), LocalName::from("td"));
+        let input = "<b>Hello</b> World!";
+        let output = strip_html_fragment(input, context);
+        assert_eq!(output, "Hello World!");
+    }
+
+    #[test]
+    fn strip_with_block_separators() {
+        let options = Options {
+            block_separators: true,
+            ..Options::default()
+        };
+
+        let input = "<p>Hello</p><p>World!</p>";
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Hello\nWorld!");
+
+        let input = "<div>a<div>b</div>c</div>";
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "a\nb\nc");
+
+        let input = "<html>Hello<br>World!</html>";
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Hello\nWorld!");
+
+        let input = "<html>a<span>b</span>c</html>";
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "abc");
+
+        // A block element followed by a sibling text node, and block-level text wrapped in an
+        // inline element, both need a separator even though neither is a text node whose
+        // immediate parent is block-level.
+        let input = "<p>a</p>b";
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "a\nb");
+
+        let input = "<p><span>a</span></p><p><span>b</span></p>";
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "a\nb");
+    }
+
+    #[test]
+    fn strip_excluded_script_and_style() {
+        let options = Options {
+            excluded_tags: ExcludedTags::default()
+                .with(ExcludedTag::Script)
+                .with(ExcludedTag::Style),
+            ..Options::default()
+        };
+
+        let input = r"<html><script>var a = 1;</script><style>body {}</style>Hello</html>";
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Hello");
+    }
+
+    #[test]
+    fn strip_excluded_template() {
+        let options = Options {
+            excluded_tags: ExcludedTags::default().with(ExcludedTag::Template),
+            ..Options::default()
+        };
+
+        let input = r#"<html>aaa <template id="aaa">bbb </template><title>ccc ddd</title></html>"#;
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "aaa ccc ddd");
+    }
+
+    #[test]
+    fn strip_with_img_alt() {
+        let options = Options {
+            media: MediaOptions {
+                alt: true,
+                ..MediaOptions::default()
+            },
+            ..Options::default()
+        };
+
+        let input = r#"<html><img src="logo.png" alt="Our logo"></html>"#;
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Our logo");
+
+        let input = r#"<html>Welcome <img src="logo.png" alt="Our logo">!</html>"#;
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Welcome Our logo!");
+
+        let options = Options {
+            media: MediaOptions {
+                alt: true,
+                title: true,
+                ..MediaOptions::default()
+            },
+            ..Options::default()
+        };
+
+        let input = r#"<html><img src="logo.png" alt="" title="Our logo"></html>"#;
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Our logo");
+    }
+
+    #[test]
+    fn strip_with_link_href() {
+        let options = Options {
+            media: MediaOptions {
+                href: true,
+                ..MediaOptions::default()
+            },
+            ..Options::default()
+        };
+
+        let input = r#"<html><a href="https://example.com">Example</a></html>"#;
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Example (https://example.com)");
+
+        let input = r#"<html><a href="https://example.com">Click <em>here</em></a></html>"#;
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Click here (https://example.com)");
+
+        // An empty href is treated like a missing one: no bare "()" suffix.
+        let input = r#"<html><a href="">Example</a></html>"#;
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Example");
+
+        // An `<a>` whose only content is an `<img>` never gets an `AppendText` call, so its
+        // suffix has to be picked up via the `<img>`'s void text instead.
+        let options = Options {
+            media: MediaOptions {
+                href: true,
+                alt: true,
+                ..MediaOptions::default()
+            },
+            ..Options::default()
+        };
+        let input = r#"<html><a href="https://example.com"><img alt="Example"></a></html>"#;
+        let output = strip_html_tags_with_options(input, options);
+        assert_eq!(output, "Example (https://example.com)");
+    }
+
+    #[test]
+    fn strip_reader() {
+        let input = b"<html>Hello<div>World!</div></html>".as_slice();
+        let output = strip_html_tags_reader(input).unwrap();
+        assert_eq!(output, "HelloWorld!");
+    }
+
     #[test]
     fn malformed() {
-        let input = r#"<html>a<b</html>"#;
+        let input = r"<html>a<b</html>";
         let output = strip_html_tags(input);
         assert_eq!(output, "a");
 
-        let input = r#"<html>a < b</html>"#;
+        let input = r"<html>a < b</html>";
         let output = strip_html_tags(input);
         assert_eq!(output, "a < b");
 
-        let input = r#"<html>a>b</html>"#;
+        let input = r"<html>a>b</html>";
         let output = strip_html_tags(input);
         assert_eq!(output, "a>b");
 
-        let input = r#"<html>a > b</html>"#;
+        let input = r"<html>a > b</html>";
         let output = strip_html_tags(input);
         assert_eq!(output, "a > b");
     }
+
+    #[test]
+    fn malformed_reports_parse_errors() {
+        let input = r"<html>a<b</html>";
+        let (output, errors) = strip_html_tags_with_errors(input);
+        assert_eq!(output, "a");
+        assert!(!errors.is_empty());
+
+        let input = "<!DOCTYPE html><html><body>Hello World!</body></html>";
+        let (output, errors) = strip_html_tags_with_errors(input);
+        assert_eq!(output, "Hello World!");
+        assert!(errors.is_empty());
+    }
 }